@@ -4,11 +4,22 @@
 use std::process::Command;
 
 use anyhow::{Context, Result};
-use xshell::Shell;
+use xshell::{cmd, Shell};
 
 type TaskFn = fn(&Shell) -> Result<()>;
 
-const TASKS: &[(&str, TaskFn)] = &[];
+/// A single automation task exposed through `cargo xtask <name>`.
+struct Task {
+    name: &'static str,
+    description: &'static str,
+    run: TaskFn,
+}
+
+const TASKS: &[Task] = &[Task {
+    name: "sync-common",
+    description: "Sync common/ infra files into a target repo: sync-common <infra> <target> <commit>",
+    run: task_sync_common,
+}];
 
 fn main() {
     if let Err(e) = try_main() {
@@ -34,22 +45,68 @@ fn try_main() -> Result<()> {
     let task = std::env::args().nth(1);
 
     let sh = xshell::Shell::new()?;
-    if let Some(cmd) = task.as_deref() {
-        let f = TASKS
-            .iter()
-            .find_map(|(k, f)| (*k == cmd).then_some(*f))
-            .unwrap_or(print_help);
-        f(&sh)
-    } else {
-        print_help(&sh)?;
-        Ok(())
+    let Some(name) = task.as_deref() else {
+        return print_help();
+    };
+
+    match TASKS.iter().find(|t| t.name == name) {
+        Some(task) => (task.run)(&sh),
+        None => {
+            eprintln!("error: unknown task '{name}'");
+            if let Some(suggestion) = closest_task(name) {
+                eprintln!("  did you mean '{suggestion}'?");
+            }
+            print_help()?;
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run the common-file syncer against a target repository.
+fn task_sync_common(sh: &Shell) -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(2).collect();
+    let [infra, target, commit] = args.as_slice() else {
+        anyhow::bail!("usage: cargo xtask sync-common <infra> <target> <commit>");
+    };
+
+    cmd!(sh, "cargo run -q -p sync-common -- {infra} {target} {commit}")
+        .run()
+        .context("Running common-file syncer")?;
+    Ok(())
+}
+
+/// Find the known task name closest to `input`, for typo suggestions.
+fn closest_task(input: &str) -> Option<&'static str> {
+    TASKS
+        .iter()
+        .map(|t| (levenshtein(input, t.name), t.name))
+        .min_by_key(|(d, _)| *d)
+        // Only suggest when the names are plausibly the same intent.
+        .filter(|(d, _)| *d <= 3)
+        .map(|(_, name)| name)
+}
+
+/// Classic Levenshtein edit distance between two strings.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
     }
+    prev[b.len()]
 }
 
-fn print_help(_sh: &Shell) -> Result<()> {
+fn print_help() -> Result<()> {
     println!("Available tasks:");
-    for (name, _) in TASKS {
-        println!("  {name}");
+    for task in TASKS {
+        println!("  {:<16} {}", task.name, task.description);
     }
     Ok(())
 }
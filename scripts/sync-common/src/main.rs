@@ -1,30 +1,62 @@
 use anyhow::{Context, Result};
+use git2::{Delta, DiffOptions, ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Write as _;
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
-use xshell::{cmd, Shell};
+use walkdir::WalkDir;
 
 const COMMIT_MARKER: &str = ".bootc-dev-infra-commit.txt";
+const MANIFEST_MARKER: &str = ".bootc-dev-infra-manifest.txt";
+const SYNC_CONFIG: &str = ".bootc-dev-infra-sync.toml";
 
 /// Git operations for querying repository history
 struct GitOps;
 
 impl GitOps {
+    /// Build a tree-to-tree diff between two commits, restricted to `prefix`.
+    fn diff_between<'repo>(
+        repo: &'repo Repository,
+        old_commit: &str,
+        new_commit: &str,
+        prefix: &str,
+    ) -> Result<git2::Diff<'repo>> {
+        let old_tree = repo
+            .revparse_single(old_commit)
+            .with_context(|| format!("Resolving commit {old_commit}"))?
+            .peel_to_commit()?
+            .tree()?;
+        let new_tree = repo
+            .revparse_single(new_commit)
+            .with_context(|| format!("Resolving commit {new_commit}"))?
+            .peel_to_commit()?
+            .tree()?;
+
+        let mut opts = DiffOptions::new();
+        opts.pathspec(prefix);
+        let diff = repo
+            .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut opts))
+            .context("Diffing infra trees")?;
+        Ok(diff)
+    }
+
     /// Get list of files deleted between two commits with given prefix
     fn get_deleted_files(
-        sh: &Shell,
         repo_path: &Path,
         old_commit: &str,
         new_commit: &str,
         prefix: &str,
     ) -> Result<Vec<String>> {
-        let _dir = sh.push_dir(repo_path);
-        let output = cmd!(sh, "git diff --name-only --diff-filter=D {old_commit} {new_commit} -- {prefix}")
-            .read()
-            .context("Failed to run git diff")?;
-
-        let files = output
-            .lines()
-            .map(|s| s.to_string())
-            .filter(|s| !s.is_empty())
+        let repo = Repository::open(repo_path)
+            .with_context(|| format!("Opening repository: {}", repo_path.display()))?;
+        let diff = Self::diff_between(&repo, old_commit, new_commit, prefix)?;
+
+        let files = diff
+            .deltas()
+            .filter(|delta| delta.status() == Delta::Deleted)
+            .filter_map(|delta| delta.old_file().path().map(|p| p.to_string_lossy().into_owned()))
             .collect();
 
         Ok(files)
@@ -32,21 +64,191 @@ impl GitOps {
 
     /// Check if there are any changes between commits for given prefix
     fn has_changes(
-        sh: &Shell,
         repo_path: &Path,
         old_commit: &str,
         new_commit: &str,
         prefix: &str,
     ) -> Result<bool> {
-        let _dir = sh.push_dir(repo_path);
-        // git diff --quiet returns exit code 1 if there are differences
-        let result = cmd!(sh, "git diff --quiet {old_commit} {new_commit} -- {prefix}").run();
+        let repo = Repository::open(repo_path)
+            .with_context(|| format!("Opening repository: {}", repo_path.display()))?;
+        let diff = Self::diff_between(&repo, old_commit, new_commit, prefix)?;
+        Ok(!diff.deltas().is_empty())
+    }
 
-        match result {
-            Ok(_) => Ok(false), // No changes
-            Err(_) => Ok(true), // Has changes (exit code 1)
+    /// Resolve the blob OID of `path` at `commit`, or `None` if absent in that tree.
+    fn blob_oid_at(repo: &Repository, commit: &str, path: &str) -> Result<Option<Oid>> {
+        let tree = repo
+            .revparse_single(commit)
+            .with_context(|| format!("Resolving commit {commit}"))?
+            .peel_to_commit()?
+            .tree()?;
+        match tree.get_path(Path::new(path)) {
+            Ok(entry) => Ok(Some(entry.id())),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e).context("Looking up tree entry"),
         }
     }
+
+    /// Resolve the blob OID and unix file mode of `path` at `commit`.
+    fn blob_entry_at(repo: &Repository, commit: &str, path: &str) -> Result<Option<(Oid, u32)>> {
+        let tree = repo
+            .revparse_single(commit)
+            .with_context(|| format!("Resolving commit {commit}"))?
+            .peel_to_commit()?
+            .tree()?;
+        match tree.get_path(Path::new(path)) {
+            Ok(entry) => {
+                let mode = (entry.filemode() as u32) & 0o777;
+                let mode = if mode == 0 { 0o644 } else { mode };
+                Ok(Some((entry.id(), mode)))
+            }
+            Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+            Err(e) => Err(e).context("Looking up tree entry"),
+        }
+    }
+
+    /// List every blob path under `prefix` present in `commit`'s tree.
+    fn list_tree_files(repo: &Repository, commit: &str, prefix: &str) -> Result<Vec<String>> {
+        let tree = repo
+            .revparse_single(commit)
+            .with_context(|| format!("Resolving commit {commit}"))?
+            .peel_to_commit()?
+            .tree()?;
+
+        let mut files = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                if let Some(name) = entry.name() {
+                    let full = format!("{root}{name}");
+                    if full.starts_with(prefix) {
+                        files.push(full);
+                    }
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+        Ok(files)
+    }
+}
+
+/// What was synced for a single path at the last sync.
+#[derive(Clone)]
+struct ManifestEntry {
+    /// Git blob OID of the version written, used as the three-way merge BASE.
+    oid: Oid,
+    /// blake3 content hash of that version, used to short-circuit comparisons.
+    hash: String,
+}
+
+/// Record of what was synced to a target, keyed by target-relative path.
+///
+/// The OID serves as the BASE in the three-way merge on the next run; the
+/// content hash lets unchanged files be detected without re-hashing blobs.
+#[derive(Default)]
+struct Manifest {
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Parse a manifest file; each line is `<oid> <hash> <relative-path>`.
+    fn parse(content: &str) -> Result<Self> {
+        let mut entries = BTreeMap::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(3, ' ');
+            let oid = parts.next();
+            let hash = parts.next();
+            let path = parts.next();
+            let (Some(oid), Some(hash), Some(path)) = (oid, hash, path) else {
+                anyhow::bail!("Malformed manifest line: {line}");
+            };
+            let oid = Oid::from_str(oid).with_context(|| format!("Invalid OID: {oid}"))?;
+            entries.insert(
+                path.to_string(),
+                ManifestEntry {
+                    oid,
+                    hash: hash.to_string(),
+                },
+            );
+        }
+        Ok(Self { entries })
+    }
+
+    /// Serialize to the `<oid> <hash> <relative-path>` line format.
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (path, entry) in &self.entries {
+            out.push_str(&format!("{} {} {path}\n", entry.oid, entry.hash));
+        }
+        out
+    }
+}
+
+/// Per-target sync configuration read from `.bootc-dev-infra-sync.toml`.
+///
+/// A target repository can opt into a subset of `common/` by listing globs
+/// (globset syntax); an empty `include` means "everything". Each pattern is
+/// matched against the whole target-relative path: `*` also matches `/`, and
+/// unlike gitignore a pattern without a slash does not match a file of that
+/// name in a subdirectory.
+#[derive(Default, Deserialize)]
+struct SyncConfig {
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// Compiled include/exclude matcher applied to target-relative paths.
+struct SyncFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+    has_include: bool,
+}
+
+impl SyncFilter {
+    /// Load the filter from the target's config file, if any.
+    fn load(target_path: &Path) -> Result<Self> {
+        let config_path = target_path.join(SYNC_CONFIG);
+        let config = if config_path.exists() {
+            let content = std::fs::read_to_string(&config_path)
+                .with_context(|| format!("Reading {}", config_path.display()))?;
+            toml::from_str(&content)
+                .with_context(|| format!("Parsing {}", config_path.display()))?
+        } else {
+            SyncConfig::default()
+        };
+        Self::from_config(&config)
+    }
+
+    fn from_config(config: &SyncConfig) -> Result<Self> {
+        let build = |patterns: &[String]| -> Result<GlobSet> {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(
+                    Glob::new(pattern)
+                        .with_context(|| format!("Invalid glob pattern: {pattern}"))?,
+                );
+            }
+            builder.build().context("Building glob set")
+        };
+        Ok(Self {
+            include: build(&config.include)?,
+            exclude: build(&config.exclude)?,
+            has_include: !config.include.is_empty(),
+        })
+    }
+
+    /// Whether a target-relative path should be synced.
+    fn matches(&self, rel: &str) -> bool {
+        if self.has_include && !self.include.is_match(rel) {
+            return false;
+        }
+        !self.exclude.is_match(rel)
+    }
 }
 
 /// File operations for syncing
@@ -68,11 +270,122 @@ impl FileOps {
     /// Write the current commit to target repository marker file
     fn write_commit_marker(target_path: &Path, commit: &str) -> Result<()> {
         let marker_path = target_path.join(COMMIT_MARKER);
-        std::fs::write(&marker_path, format!("{}\n", commit))
+        Self::atomic_write(&marker_path, format!("{}\n", commit).as_bytes(), 0o644)
             .context("Failed to write commit marker")?;
         Ok(())
     }
 
+    /// Write `data` to `path` atomically: write a temporary sibling, fsync it,
+    /// then `rename` into place so a concurrent reader never observes a
+    /// truncated file. Parent directories are created on demand.
+    fn atomic_write(path: &Path, data: &[u8], mode: u32) -> Result<()> {
+        match Self::atomic_write_inner(path, data, mode) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("Creating parent directory: {}", parent.display())
+                    })?;
+                }
+                Self::atomic_write_inner(path, data, mode)
+                    .with_context(|| format!("Atomically writing {}", path.display()))
+            }
+            other => other.with_context(|| format!("Atomically writing {}", path.display())),
+        }
+    }
+
+    fn atomic_write_inner(path: &Path, data: &[u8], mode: u32) -> std::io::Result<()> {
+        let parent = path.parent().unwrap_or_else(|| Path::new("."));
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("tmpfile");
+        // The pid keeps concurrent syncers from colliding on the temp path.
+        let tmp = parent.join(format!(".{file_name}.{}.tmp", std::process::id()));
+
+        {
+            let mut f = std::fs::File::create(&tmp)?;
+            f.write_all(data)?;
+            f.set_permissions(std::fs::Permissions::from_mode(mode))?;
+            f.sync_all()?;
+        }
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Read the sync manifest from the target repository, if present.
+    fn read_manifest(target_path: &Path) -> Result<Option<Manifest>> {
+        let manifest_path = target_path.join(MANIFEST_MARKER);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        let content =
+            std::fs::read_to_string(&manifest_path).context("Failed to read manifest")?;
+        Ok(Some(Manifest::parse(&content)?))
+    }
+
+    /// Write the sync manifest to the target repository.
+    fn write_manifest(target_path: &Path, manifest: &Manifest) -> Result<()> {
+        let manifest_path = target_path.join(MANIFEST_MARKER);
+        Self::atomic_write(&manifest_path, manifest.render().as_bytes(), 0o644)
+            .context("Failed to write manifest")?;
+        Ok(())
+    }
+
+    /// blake3 content hash, as a lowercase hex string.
+    fn hash_bytes(data: &[u8]) -> String {
+        blake3::hash(data).to_hex().to_string()
+    }
+
+    /// blake3 content hash of a file on disk.
+    fn hash_file(path: &Path) -> Result<String> {
+        let data = std::fs::read(path).with_context(|| format!("Reading {}", path.display()))?;
+        Ok(Self::hash_bytes(&data))
+    }
+
+    /// Write `content` to `target_file` with `mode`, skipping the write when
+    /// the existing file already has identical content, and verifying the
+    /// bytes on disk afterward. Returns whether a write actually happened.
+    fn write_blob(target_file: &Path, content: &[u8], mode: u32) -> Result<bool> {
+        let want = Self::hash_bytes(content);
+        if target_file.exists() && Self::hash_file(target_file)? == want {
+            return Ok(false);
+        }
+        Self::atomic_write(target_file, content, mode)?;
+        let got = Self::hash_file(target_file)?;
+        if got != want {
+            anyhow::bail!(
+                "Integrity check failed after writing {}: {got} != {want}",
+                target_file.display()
+            );
+        }
+        Ok(true)
+    }
+
+    /// Scan the target for leftover `<file>.upstream` conflict sidecars and
+    /// return the relative paths they belong to. A lingering sidecar means a
+    /// conflict the human has not yet resolved (by removing it).
+    fn find_unresolved_conflicts(target_path: &Path) -> Result<Vec<String>> {
+        let mut out = Vec::new();
+        let walker = WalkDir::new(target_path)
+            .into_iter()
+            .filter_entry(|e| e.file_name() != ".git");
+        for entry in walker {
+            let entry = entry.context("Scanning target for conflict sidecars")?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel = entry
+                .path()
+                .strip_prefix(target_path)
+                .expect("walkdir entry is under target")
+                .to_string_lossy();
+            if let Some(base) = rel.strip_suffix(".upstream") {
+                out.push(base.to_string());
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+
     /// Remove a file if it exists
     fn remove_file(file_path: &Path) -> Result<()> {
         if file_path.exists() && file_path.is_file() {
@@ -83,19 +396,45 @@ impl FileOps {
         Ok(())
     }
 
-    /// Sync directory using rsync
-    fn sync_directory(sh: &Shell, source: &Path, target: &Path) -> Result<()> {
-        let source_str = format!("{}/", source.display());
-        let target_str = target.display().to_string();
-
-        cmd!(sh, "rsync -av {source_str} {target_str}")
-            .run()
-            .context("Failed to sync directory with rsync")?;
+    /// Copy every file under `source` into `target`, filtered by `filter`.
+    ///
+    /// Replaces the blanket `rsync -av`: each file is walked with `walkdir`,
+    /// matched against the target's include/exclude globs, and written
+    /// atomically while preserving its source mode.
+    fn copy_tree(source: &Path, target: &Path, filter: &SyncFilter) -> Result<()> {
+        for entry in WalkDir::new(source) {
+            let entry = entry.context("Walking common/ tree")?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel = entry
+                .path()
+                .strip_prefix(source)
+                .expect("walkdir entry is under source");
+            let rel_str = rel.to_string_lossy();
+            if !filter.matches(&rel_str) {
+                continue;
+            }
 
+            let content = std::fs::read(entry.path())
+                .with_context(|| format!("Reading {}", entry.path().display()))?;
+            let mode = entry.metadata().map(|m| m.permissions().mode()).unwrap_or(0o644);
+            Self::atomic_write(&target.join(rel), &content, mode)?;
+        }
         Ok(())
     }
 }
 
+/// Outcome of a sync run.
+struct SyncReport {
+    /// Whether any file in the target was created, updated, or removed.
+    changed: bool,
+    /// Target-relative paths that could not be merged automatically; for each
+    /// one the upstream version was written as `<path>.upstream` (an empty
+    /// file when upstream deleted the path).
+    conflicts: Vec<String>,
+}
+
 /// Main syncer that orchestrates the sync process
 struct CommonFileSyncer;
 
@@ -105,82 +444,228 @@ impl CommonFileSyncer {
         infra_path: &Path,
         target_path: &Path,
         current_commit: &str,
-    ) -> Result<bool> {
+    ) -> Result<SyncReport> {
         let common_path = infra_path.join("common");
         if !common_path.exists() {
             anyhow::bail!("Common directory not found: {}", common_path.display());
         }
 
+        let filter = SyncFilter::load(target_path)?;
         let previous_commit = FileOps::read_commit_marker(target_path)?;
 
-        match previous_commit {
+        let mut report = match previous_commit {
             Some(prev) => Self::sync_incremental(
                 infra_path,
                 target_path,
-                &common_path,
                 &prev,
                 current_commit,
-            ),
-            None => Self::sync_initial(target_path, &common_path, current_commit),
+                &filter,
+            )?,
+            None => {
+                Self::sync_initial(infra_path, target_path, &common_path, current_commit, &filter)?
+            }
+        };
+
+        // Re-detect conflict sidecars left from earlier runs so a target stays
+        // failing until every `<file>.upstream` is removed, even on a run that
+        // itself found no upstream changes.
+        for rel in FileOps::find_unresolved_conflicts(target_path)? {
+            if !report.conflicts.contains(&rel) {
+                report.conflicts.push(rel);
+            }
         }
+        report.conflicts.sort();
+
+        Ok(report)
     }
 
-    /// Handle incremental sync when previous sync exists
+    /// Handle incremental sync when previous sync exists.
+    ///
+    /// Each synced file is resolved with a three-way merge against the BASE
+    /// recorded in the manifest, the LOCAL content in the target, and the
+    /// UPSTREAM blob at `current_commit`.
     fn sync_incremental(
         infra_path: &Path,
         target_path: &Path,
-        common_path: &Path,
         previous_commit: &str,
         current_commit: &str,
-    ) -> Result<bool> {
+        filter: &SyncFilter,
+    ) -> Result<SyncReport> {
         println!("Previous sync: {}", previous_commit);
         println!("Current commit: {}", current_commit);
 
-        let sh = Shell::new()?;
         let has_changes =
-            GitOps::has_changes(&sh, infra_path, previous_commit, current_commit, "common/")?;
+            GitOps::has_changes(infra_path, previous_commit, current_commit, "common/")?;
 
         if !has_changes {
             println!("No changes in common/ directory, skipping");
-            return Ok(false);
+            return Ok(SyncReport {
+                changed: false,
+                conflicts: Vec::new(),
+            });
         }
 
         println!("Syncing changes from common/ directory");
 
+        let repo = Repository::open(infra_path)
+            .with_context(|| format!("Opening repository: {}", infra_path.display()))?;
+        let base = FileOps::read_manifest(target_path)?.unwrap_or_default();
+        let mut manifest = Manifest::default();
+        let mut conflicts = Vec::new();
+
         // Remove deleted files
         let deleted_files =
-            GitOps::get_deleted_files(&sh, infra_path, previous_commit, current_commit, "common/")?;
+            GitOps::get_deleted_files(infra_path, previous_commit, current_commit, "common/")?;
 
         for file_path in deleted_files {
             // Strip 'common/' prefix to get target path
             if let Some(rel_path) = file_path.strip_prefix("common/") {
+                // Don't chase deletions of files this target never subscribed to.
+                if !filter.matches(rel_path) {
+                    continue;
+                }
                 let target_file = target_path.join(rel_path);
-                FileOps::remove_file(&target_file)?;
+                if !target_file.exists() {
+                    continue;
+                }
+
+                // Only follow the deletion when LOCAL still matches BASE; a local
+                // edit must not be silently clobbered just because upstream removed
+                // the file. Treat the divergence as a conflict instead.
+                let base_oid = match base.entries.get(rel_path) {
+                    Some(e) => Some(e.oid),
+                    None => GitOps::blob_oid_at(&repo, previous_commit, &file_path)?,
+                };
+                let local = Oid::hash_file(ObjectType::Blob, &target_file).with_context(|| {
+                    format!("Hashing local file: {}", target_file.display())
+                })?;
+
+                if Some(local) == base_oid {
+                    FileOps::remove_file(&target_file)?;
+                } else {
+                    // Leave LOCAL in place and drop a sidecar marking the upstream
+                    // deletion; the sidecar keeps the conflict unresolved until a
+                    // human removes it.
+                    let conflict_file = target_path.join(format!("{rel_path}.upstream"));
+                    FileOps::write_blob(&conflict_file, &[], 0o644)?;
+                    println!("  CONFLICT: {rel_path} (deleted upstream, local edit kept)");
+                    conflicts.push(rel_path.to_string());
+                }
             }
         }
 
-        // Sync all current files
-        FileOps::sync_directory(&sh, common_path, target_path)?;
+        // Three-way merge every file present upstream at current_commit.
+        for full_path in GitOps::list_tree_files(&repo, current_commit, "common/")? {
+            let rel = match full_path.strip_prefix("common/") {
+                Some(r) if !r.is_empty() => r.to_string(),
+                _ => continue,
+            };
+            if !filter.matches(&rel) {
+                continue;
+            }
+            let target_file = target_path.join(&rel);
+
+            let (upstream, mode) = GitOps::blob_entry_at(&repo, current_commit, &full_path)?
+                .context("Upstream file vanished during sync")?;
+            let base_entry = base.entries.get(&rel);
+            // When a target predates the manifest (synced by the old code, so it
+            // has a commit marker but no manifest), fall back to the blob at
+            // `previous_commit` as BASE. Otherwise every upstream-modified file
+            // would look like a conflict on the first run under the new code.
+            let base_oid = match base_entry {
+                Some(e) => Some(e.oid),
+                None => GitOps::blob_oid_at(&repo, previous_commit, &full_path)?,
+            };
+
+            // Fast path: upstream unchanged and the on-disk target still matches
+            // the stored hash, so nothing can have changed. Skip reading the blob.
+            if Some(upstream) == base_oid && target_file.exists() {
+                if let Some(entry) = base_entry {
+                    if FileOps::hash_file(&target_file)? == entry.hash {
+                        manifest.entries.insert(rel, entry.clone());
+                        continue;
+                    }
+                }
+            }
+
+            let local = if target_file.exists() {
+                Some(Oid::hash_file(ObjectType::Blob, &target_file).with_context(|| {
+                    format!("Hashing local file: {}", target_file.display())
+                })?)
+            } else {
+                None
+            };
+
+            let content = repo.find_blob(upstream)?.content().to_vec();
+            let hash = FileOps::hash_bytes(&content);
+
+            if local == base_oid {
+                // LOCAL unchanged since last sync: take UPSTREAM.
+                FileOps::write_blob(&target_file, &content, mode)?;
+            } else if local == Some(upstream) {
+                // Already converged with UPSTREAM; nothing to do.
+            } else if Some(upstream) == base_oid {
+                // UPSTREAM unchanged; preserve the local edit.
+            } else {
+                // All three differ: surface a conflict for CI to resolve.
+                let conflict_file = target_path.join(format!("{rel}.upstream"));
+                FileOps::write_blob(&conflict_file, &content, mode)?;
+                println!("  CONFLICT: {rel} (upstream written as {rel}.upstream)");
+                conflicts.push(rel.clone());
+            }
+
+            manifest.entries.insert(rel, ManifestEntry { oid: upstream, hash });
+        }
 
-        // Update commit marker
+        // Advance BASE to UPSTREAM so the merge converges: a resolved conflict
+        // (LOCAL hand-merged, sidecar removed) won't be re-flagged next run.
+        // Unresolved conflicts persist as `<file>.upstream` sidecars, which
+        // `sync` re-detects independently to keep CI failing until removed.
+        FileOps::write_manifest(target_path, &manifest)?;
         FileOps::write_commit_marker(target_path, current_commit)?;
 
-        Ok(true)
+        Ok(SyncReport {
+            changed: true,
+            conflicts,
+        })
     }
 
     /// Handle initial sync when no previous sync exists
     fn sync_initial(
+        infra_path: &Path,
         target_path: &Path,
         common_path: &Path,
         current_commit: &str,
-    ) -> Result<bool> {
+        filter: &SyncFilter,
+    ) -> Result<SyncReport> {
         println!("First sync - copying all files");
 
-        let sh = Shell::new()?;
-        FileOps::sync_directory(&sh, common_path, target_path)?;
+        FileOps::copy_tree(common_path, target_path, filter)?;
+
+        // Record the synced blob hashes so subsequent runs can three-way merge.
+        let repo = Repository::open(infra_path)
+            .with_context(|| format!("Opening repository: {}", infra_path.display()))?;
+        let mut manifest = Manifest::default();
+        for full_path in GitOps::list_tree_files(&repo, current_commit, "common/")? {
+            if let Some(rel) = full_path.strip_prefix("common/") {
+                if rel.is_empty() || !filter.matches(rel) {
+                    continue;
+                }
+                if let Some(oid) = GitOps::blob_oid_at(&repo, current_commit, &full_path)? {
+                    let hash = FileOps::hash_bytes(repo.find_blob(oid)?.content());
+                    manifest
+                        .entries
+                        .insert(rel.to_string(), ManifestEntry { oid, hash });
+                }
+            }
+        }
+        FileOps::write_manifest(target_path, &manifest)?;
         FileOps::write_commit_marker(target_path, current_commit)?;
 
-        Ok(true)
+        Ok(SyncReport {
+            changed: true,
+            conflicts: Vec::new(),
+        })
     }
 }
 
@@ -196,7 +681,15 @@ fn main() -> Result<()> {
     let target_path = PathBuf::from(&args[2]);
     let current_commit = &args[3];
 
-    CommonFileSyncer::sync(&infra_path, &target_path, current_commit)?;
+    let report = CommonFileSyncer::sync(&infra_path, &target_path, current_commit)?;
+
+    if !report.conflicts.is_empty() {
+        eprintln!("error: {} unresolved conflict(s):", report.conflicts.len());
+        for path in &report.conflicts {
+            eprintln!("  {path} (upstream written as {path}.upstream)");
+        }
+        std::process::exit(1);
+    }
 
     Ok(())
 }
@@ -206,6 +699,7 @@ mod tests {
     use super::*;
     use std::fs;
     use tempfile::TempDir;
+    use xshell::{cmd, Shell};
 
     #[test]
     fn test_read_commit_marker_exists() {
@@ -234,6 +728,36 @@ mod tests {
         assert_eq!(content, "def456\n");
     }
 
+    #[test]
+    fn test_atomic_write_creates_parent_dirs() {
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("nested/deep/file.txt");
+
+        FileOps::atomic_write(&target, b"payload", 0o644).unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "payload");
+        // No leftover temp sibling after the rename.
+        let siblings: Vec<_> = fs::read_dir(target.parent().unwrap())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(siblings, vec![std::ffi::OsString::from("file.txt")]);
+    }
+
+    #[test]
+    fn test_write_blob_skips_identical_content() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("f.txt");
+
+        // First write lands on disk.
+        assert!(FileOps::write_blob(&file, b"same", 0o644).unwrap());
+        // Identical content is a no-op.
+        assert!(!FileOps::write_blob(&file, b"same", 0o644).unwrap());
+        // Changed content writes again.
+        assert!(FileOps::write_blob(&file, b"different", 0o644).unwrap());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "different");
+    }
+
     #[test]
     fn test_remove_file_exists() {
         let dir = TempDir::new().unwrap();
@@ -307,7 +831,7 @@ mod tests {
         );
 
         assert!(result.is_ok());
-        assert!(result.unwrap());
+        assert!(result.unwrap().changed);
 
         // Verify files were synced
         assert!(target_dir.path().join("file1.txt").exists());
@@ -351,7 +875,7 @@ mod tests {
         );
 
         assert!(result.is_ok());
-        assert!(result.unwrap());
+        assert!(result.unwrap().changed);
 
         // Verify new file exists
         assert!(target_dir.path().join("file3.txt").exists());
@@ -392,7 +916,7 @@ mod tests {
         );
 
         assert!(result.is_ok());
-        assert!(result.unwrap());
+        assert!(result.unwrap().changed);
 
         // Verify file2.txt was deleted
         assert!(!target_dir.path().join("file2.txt").exists());
@@ -418,7 +942,7 @@ mod tests {
         );
 
         assert!(result.is_ok());
-        assert!(!result.unwrap()); // Should return false for no changes
+        assert!(!result.unwrap().changed); // Should return false for no changes
     }
 
     #[test]
@@ -445,4 +969,149 @@ mod tests {
         assert!(target_dir.path().join("file1.txt").exists());
         assert!(target_dir.path().join("file2.txt").exists());
     }
+
+    #[test]
+    fn test_sync_respects_exclude_patterns() {
+        let infra_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let commit = setup_infra_repo(infra_dir.path());
+
+        // Target opts out of file2.txt.
+        fs::write(
+            target_dir.path().join(SYNC_CONFIG),
+            "exclude = [\"file2.txt\"]\n",
+        )
+        .unwrap();
+
+        CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &commit).unwrap();
+
+        assert!(target_dir.path().join("file1.txt").exists());
+        assert!(!target_dir.path().join("file2.txt").exists());
+    }
+
+    // Helper to commit the current state of an infra repo and return the hash.
+    fn commit_infra_repo(dir: &Path, message: &str) -> String {
+        let sh = Shell::new().unwrap();
+        let _d = sh.push_dir(dir);
+        cmd!(sh, "git add .").run().unwrap();
+        cmd!(sh, "git commit -m {message}").run().unwrap();
+        cmd!(sh, "git rev-parse HEAD").read().unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn test_incremental_sync_reports_conflict() {
+        let infra_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let initial_commit = setup_infra_repo(infra_dir.path());
+        CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &initial_commit).unwrap();
+
+        // Diverge both sides: the target edits file1 locally...
+        fs::write(target_dir.path().join("file1.txt"), "local edit").unwrap();
+        // ...while upstream edits the same file differently.
+        fs::write(infra_dir.path().join("common/file1.txt"), "upstream edit").unwrap();
+        let new_commit = commit_infra_repo(infra_dir.path(), "Upstream edit file1");
+
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+
+        assert!(report.changed);
+        assert_eq!(report.conflicts, vec!["file1.txt".to_string()]);
+        // Local content is preserved and the upstream version lands alongside.
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file1.txt")).unwrap(),
+            "local edit"
+        );
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file1.txt.upstream")).unwrap(),
+            "upstream edit"
+        );
+    }
+
+    #[test]
+    fn test_incremental_sync_preserves_untouched_local_edit() {
+        let infra_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let initial_commit = setup_infra_repo(infra_dir.path());
+        CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &initial_commit).unwrap();
+
+        // The target edits file1; upstream leaves file1 alone but adds file3.
+        fs::write(target_dir.path().join("file1.txt"), "local edit").unwrap();
+        fs::write(infra_dir.path().join("common/file3.txt"), "content3").unwrap();
+        let new_commit = commit_infra_repo(infra_dir.path(), "Add file3");
+
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+
+        assert!(report.conflicts.is_empty());
+        // file1 keeps the local edit since upstream did not change it.
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file1.txt")).unwrap(),
+            "local edit"
+        );
+        // file3 is picked up.
+        assert!(target_dir.path().join("file3.txt").exists());
+    }
+
+    #[test]
+    fn test_upstream_deletion_of_local_edit_is_conflict() {
+        let infra_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let initial_commit = setup_infra_repo(infra_dir.path());
+        CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &initial_commit).unwrap();
+
+        // Target edits file2; upstream deletes it.
+        fs::write(target_dir.path().join("file2.txt"), "local edit").unwrap();
+        fs::remove_file(infra_dir.path().join("common/file2.txt")).unwrap();
+        let new_commit = commit_infra_repo(infra_dir.path(), "Delete file2");
+
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+
+        assert!(report.conflicts.contains(&"file2.txt".to_string()));
+        // The local edit is preserved rather than deleted.
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file2.txt")).unwrap(),
+            "local edit"
+        );
+        // A sidecar marks the unresolved upstream deletion.
+        assert!(target_dir.path().join("file2.txt.upstream").exists());
+    }
+
+    #[test]
+    fn test_conflict_stays_until_sidecar_removed() {
+        let infra_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let initial_commit = setup_infra_repo(infra_dir.path());
+        CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &initial_commit).unwrap();
+
+        // Produce a content conflict on file1.
+        fs::write(target_dir.path().join("file1.txt"), "local edit").unwrap();
+        fs::write(infra_dir.path().join("common/file1.txt"), "upstream edit").unwrap();
+        let new_commit = commit_infra_repo(infra_dir.path(), "Upstream edit file1");
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+        assert_eq!(report.conflicts, vec!["file1.txt".to_string()]);
+
+        // A re-run with no new upstream changes still fails: the sidecar lingers.
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+        assert_eq!(report.conflicts, vec!["file1.txt".to_string()]);
+
+        // Human resolves by hand-merging LOCAL and removing the sidecar.
+        fs::write(target_dir.path().join("file1.txt"), "merged result").unwrap();
+        fs::remove_file(target_dir.path().join("file1.txt.upstream")).unwrap();
+
+        let report =
+            CommonFileSyncer::sync(infra_dir.path(), target_dir.path(), &new_commit).unwrap();
+        assert!(report.conflicts.is_empty());
+        assert_eq!(
+            fs::read_to_string(target_dir.path().join("file1.txt")).unwrap(),
+            "merged result"
+        );
+    }
 }